@@ -0,0 +1,82 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::PooledConnection;
+
+/// A streaming handle onto a single BLOB column value, opened via [`super::Sqlite::open_blob`].
+///
+/// Backed by SQLite's incremental BLOB I/O (`sqlite3_blob_*`, via `rusqlite::blob::Blob`), so
+/// reading or writing through it never materializes the whole column in memory, which matters
+/// for rows that hold multi-megabyte payloads. The handle keeps its pooled connection checked
+/// out for as long as it's alive.
+pub struct BlobHandle {
+    blob: rusqlite::blob::Blob<'static>,
+    // Keeps the connection `blob` borrows from checked out of the pool for the handle's
+    // lifetime. The `Box` gives the connection a stable heap address, so the 'static borrow
+    // above stays valid even if the `BlobHandle` itself is moved. Never read, only held.
+    _conn: Box<PooledConnection>,
+}
+
+impl BlobHandle {
+    pub(super) fn new(
+        conn: PooledConnection,
+        db: &str,
+        table: &str,
+        column: &str,
+        row_id: i64,
+        read_only: bool,
+    ) -> crate::Result<Self> {
+        let conn = Box::new(conn);
+
+        // SAFETY: `blob` borrows from `*conn`. We keep `conn` boxed so moving the `BlobHandle`
+        // never moves the `Connection` it points into, and we never hand out another reference
+        // to `*conn` for as long as `blob` is alive.
+        let conn_ref: &'static rusqlite::Connection = unsafe { &*(&**conn as *const rusqlite::Connection) };
+        let blob = conn_ref.blob_open(rusqlite::DatabaseName::Attached(db), table, column, row_id, read_only)?;
+
+        Ok(BlobHandle { blob, _conn: conn })
+    }
+
+    /// Size of the blob in bytes.
+    pub fn len(&self) -> usize {
+        self.blob.size() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Surfaces a `Busy`/`Locked` SQLite error as `WouldBlock` so callers can tell a transient,
+/// retryable condition apart from a real I/O failure.
+fn retryable(err: std::io::Error) -> std::io::Error {
+    match err.get_ref().and_then(|e| e.downcast_ref::<rusqlite::Error>()) {
+        Some(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::DatabaseBusy || e.code == rusqlite::ErrorCode::DatabaseLocked =>
+        {
+            std::io::Error::new(std::io::ErrorKind::WouldBlock, "blob I/O would block, retry later")
+        }
+        _ => err,
+    }
+}
+
+impl Read for BlobHandle {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.blob.read(buf).map_err(retryable)
+    }
+}
+
+impl Write for BlobHandle {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.blob.write(buf).map_err(retryable)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.blob.flush()
+    }
+}
+
+impl Seek for BlobHandle {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.blob.seek(pos).map_err(retryable)
+    }
+}
@@ -0,0 +1,53 @@
+use rusqlite::DatabaseName;
+
+/// A single migration step, run inside its own transaction against the schema version it is
+/// registered for. `db` is the name the real database is attached under -- see the note on
+/// [`super::Sqlite`] -- and must be used to qualify any DDL/DML the migration runs, since
+/// unqualified SQL resolves to `main`, which is always in-memory scratch. See [`run_pending`].
+pub type Migration = Box<dyn Fn(&rusqlite::Transaction<'_>, &str) -> crate::Result<()> + Send + Sync>;
+
+/// Brings the `db`-attached schema up to `target_version`, tracked via SQLite's
+/// `PRAGMA user_version`. `user_version` is scoped to `db` rather than `main`, since `main` on a
+/// [`super::Sqlite`] connection is always in-memory scratch -- see the comment on
+/// `Sqlite::new_internal` -- and the real schema only exists under its attached name.
+///
+/// `migrations[i]` is expected to take the schema from version `i` to version `i + 1`.
+/// Migrations below the database's current `user_version` are skipped; each remaining one runs
+/// inside its own transaction that also bumps `user_version` before committing, so a crash
+/// mid-migration leaves the database at the last fully-applied version rather than repeating or
+/// skipping a step. `pre_migration`, when given, is called once with the current version before
+/// any migration runs, e.g. to run an integrity check first.
+pub(super) fn run_pending(
+    conn: &mut rusqlite::Connection,
+    db: &str,
+    migrations: &[Migration],
+    target_version: i64,
+    pre_migration: Option<&(dyn Fn(i64) -> crate::Result<()> + Send + Sync)>,
+) -> crate::Result<()> {
+    let current_version: i64 = conn.pragma_query_value(Some(DatabaseName::Attached(db)), "user_version", |row| row.get(0))?;
+
+    if current_version >= target_version {
+        return Ok(());
+    }
+
+    if let Some(pre_migration) = pre_migration {
+        pre_migration(current_version)?;
+    }
+
+    for version in current_version..target_version {
+        let migration = migrations.get(version as usize).ok_or_else(|| {
+            crate::error::Error::MigrationError(format!(
+                "no migration registered to take the schema from version {} to {}",
+                version,
+                version + 1
+            ))
+        })?;
+
+        let tx = conn.transaction()?;
+        migration(&tx, db)?;
+        tx.pragma_update(Some(DatabaseName::Attached(db)), "user_version", version + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
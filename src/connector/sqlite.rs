@@ -1,29 +1,84 @@
+mod blob;
 mod connection_like;
 mod conversion;
 mod error;
+mod migrations;
 
 use crate::{
-    ast::{Id, ParameterizedValue, Query},
+    ast::{Id, Insert, ParameterizedValue, Query},
     connector::{
         queryable::{Database, Queryable, Transactional},
         ResultSet,
     },
     error::Error,
 };
+pub use blob::BlobHandle;
+pub use migrations::Migration;
+
 use connection_like::*;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::NO_PARAMS;
-use std::{collections::HashSet, convert::TryFrom, path::PathBuf};
+use rusqlite::{backup::Backup, functions::FunctionFlags, NO_PARAMS};
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use telemetry::TraceParent;
 
 type Manager = SqliteConnectionManager;
 type PooledConnection = r2d2::PooledConnection<Manager>;
 type Pool = r2d2::Pool<Manager>;
 
+/// Default number of pages copied per [`rusqlite::backup::Backup`] step when none is given.
+const DEFAULT_BACKUP_PAGES_PER_STEP: i32 = 100;
+
+/// How long to sleep before retrying a backup step that came back `Busy` or `Locked`.
+const BACKUP_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Default `busy_timeout` applied to every connection, so a writer waiting on SQLite's single
+/// writer lock retries for a while before surfacing `SQLITE_BUSY`.
+const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// SQLite's own default ceiling on bound parameters per statement (`SQLITE_LIMIT_VARIABLE_NUMBER`).
+const DEFAULT_MAX_BIND_VALUES: usize = 999;
+
 /// A connector interface for the SQLite database.
+///
+/// `main` on every connection this hands out is always in-memory scratch; the physical database
+/// at `file_path` only ever exists as a schema `ATTACH`-ed under the `db` name passed to
+/// `with_connection`, `with_transaction`, `open_blob`, `backup` and `restore` (see
+/// `attach_database`). SQLite always resolves an unqualified table name to `main`, never to an
+/// attached schema, so any raw SQL this connector runs against the real database -- including
+/// `Migration` closures -- must qualify table names with that `db` name (e.g. `db.table`) to
+/// actually reach it instead of silently landing in scratch memory.
 pub struct Sqlite {
     file_path: String,
     pool: Pool,
     test_mode: bool,
+    scalar_functions: Arc<Mutex<Vec<ScalarFunction>>>,
+    /// The URI params `file_path` was opened with, re-applied every time `file_path` is attached
+    /// onto a fresh connection. See the comment on [`Sqlite::new_internal`] for why attaching,
+    /// rather than opening `file_path` directly as `main`, is how these take effect.
+    params: SqliteParams,
+    /// Maximum rows allowed at once for a multi-row `INSERT`. `None` is unlimited.
+    max_insert_rows: Option<usize>,
+    /// Maximum number of bind parameters allowed for a single statement. `None` is unlimited.
+    /// SQLite's own default ceiling is 999 (`SQLITE_LIMIT_VARIABLE_NUMBER`).
+    max_bind_values: Option<usize>,
+}
+
+type BoxedScalarFn = dyn Fn(&[ParameterizedValue<'_>]) -> crate::Result<ParameterizedValue<'static>> + Send + Sync;
+
+/// A user-defined scalar function registered through [`Sqlite::create_scalar_function`], kept
+/// around so it can be re-applied to every connection the r2d2 pool hands out.
+struct ScalarFunction {
+    name: String,
+    n_args: i32,
+    deterministic: bool,
+    func: Arc<BoxedScalarFn>,
 }
 
 impl Transactional for Sqlite {
@@ -33,20 +88,7 @@ impl Transactional for Sqlite {
     where
         F: FnOnce(&mut Queryable) -> crate::Result<T>,
     {
-        self.with_connection_internal(db, |conn| {
-            let tx = conn.transaction()?;
-            tx.set_prepared_statement_cache_capacity(65536);
-
-            let mut connection_like = ConnectionLike::from(tx);
-            let result = f(&mut connection_like);
-
-            if result.is_ok() {
-                let tx = rusqlite::Transaction::try_from(connection_like).unwrap();
-                tx.commit()?;
-            }
-
-            result
-        })
+        self.with_transaction_internal(db, None, f)
     }
 }
 
@@ -56,11 +98,16 @@ impl Database for Sqlite {
         F: FnOnce(&mut Queryable) -> crate::Result<T>,
         Self: Sized,
     {
-        self.with_connection_internal(db, |c| f(c))
+        self.with_connection_internal(db, None, |c| f(c))
     }
 
     fn execute_on_connection<'a>(&self, db: &str, query: Query<'a>) -> crate::Result<Option<Id>> {
-        self.with_connection(&db, |conn| conn.execute(query))
+        match query {
+            Query::Insert(insert) if self.insert_needs_chunking(&insert) => {
+                self.with_transaction(db, |conn| self.execute_chunked_insert(conn, *insert))
+            }
+            other => self.with_connection(&db, |conn| conn.execute(other)),
+        }
     }
 
     fn query_on_connection<'a>(&self, db: &str, query: Query<'a>) -> crate::Result<ResultSet> {
@@ -80,30 +127,218 @@ impl Database for Sqlite {
 impl TryFrom<&str> for Sqlite {
     type Error = Error;
 
-    /// Todo connection limit configuration
     fn try_from(url: &str) -> crate::Result<Sqlite> {
         // We must handle file URLs ourselves.
         let normalized = url.trim_start_matches("file:");
-        let path = PathBuf::from(&normalized);
+
+        let (file_path, query) = match normalized.find('?') {
+            Some(idx) => (&normalized[..idx], Some(&normalized[idx + 1..])),
+            None => (normalized, None),
+        };
+
+        let path = PathBuf::from(file_path);
 
         if path.is_dir() {
-            Err(Error::DatabaseUrlIsInvalid(url.to_string()))
+            return Err(Error::DatabaseUrlIsInvalid(url.to_string()));
+        }
+
+        let params = SqliteParams::parse(query);
+        let connection_limit = params.connection_limit.unwrap_or(10);
+
+        Sqlite::new_internal(file_path.to_string(), connection_limit, params, false)
+    }
+}
+
+/// SQLite URI query parameters recognized on a `file:` connection string, e.g.
+/// `file:dev.db?mode=rwc&cache=shared&immutable=1`.
+#[derive(Debug, Default, Clone, Copy)]
+struct SqliteParams {
+    mode: Option<OpenMode>,
+    cache: Option<CacheMode>,
+    immutable: bool,
+    connection_limit: Option<u32>,
+}
+
+impl SqliteParams {
+    fn parse(query: Option<&str>) -> Self {
+        let mut params = SqliteParams::default();
+
+        let query = match query {
+            Some(query) => query,
+            None => return params,
+        };
+
+        for pair in query.split('&') {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next().unwrap_or_default();
+            let value = parts.next().unwrap_or_default();
+
+            match key {
+                "mode" => params.mode = OpenMode::parse(value),
+                "cache" => params.cache = CacheMode::parse(value),
+                "immutable" => params.immutable = value == "1",
+                "connection_limit" => params.connection_limit = value.parse().ok(),
+                _ => (),
+            }
+        }
+
+        params
+    }
+
+    /// Builds the path `attach_database` should `ATTACH` to reach `file_path` with these
+    /// params honored, as a `file:` URI SQLite's own URI parser applies `mode`/`cache`/
+    /// `immutable` from directly. `main` is never opened against `file_path` itself -- see the
+    /// comment on `Sqlite::new_internal` -- so this, plus the `query_only` pragma
+    /// `attach_database` sets when `immutable` is on, is where these params take effect.
+    fn attach_uri(self, file_path: &str) -> String {
+        if file_path == ":memory:" {
+            return file_path.to_string();
+        }
+
+        let mut query = Vec::new();
+
+        if let Some(mode) = self.mode {
+            query.push(format!("mode={}", mode.as_str()));
+        }
+
+        if let Some(cache) = self.cache {
+            query.push(format!("cache={}", cache.as_str()));
+        }
+
+        if self.immutable {
+            query.push("immutable=1".to_string());
+        }
+
+        if query.is_empty() {
+            format!("file:{}", file_path)
         } else {
-            Sqlite::new(normalized.to_string(), 10, false)
+            format!("file:{}?{}", file_path, query.join("&"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OpenMode {
+    ReadOnly,
+    ReadWrite,
+    ReadWriteCreate,
+    Memory,
+}
+
+impl OpenMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ro" => Some(OpenMode::ReadOnly),
+            "rw" => Some(OpenMode::ReadWrite),
+            "rwc" => Some(OpenMode::ReadWriteCreate),
+            "memory" => Some(OpenMode::Memory),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            OpenMode::ReadOnly => "ro",
+            OpenMode::ReadWrite => "rw",
+            OpenMode::ReadWriteCreate => "rwc",
+            OpenMode::Memory => "memory",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CacheMode {
+    Shared,
+    Private,
+}
+
+impl CacheMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "shared" => Some(CacheMode::Shared),
+            "private" => Some(CacheMode::Private),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CacheMode::Shared => "shared",
+            CacheMode::Private => "private",
         }
     }
 }
 
 impl Sqlite {
     pub fn new(file_path: String, connection_limit: u32, test_mode: bool) -> crate::Result<Sqlite> {
-        let pool = r2d2::Pool::builder()
-            .max_size(connection_limit)
-            .build(SqliteConnectionManager::memory())?;
+        Sqlite::new_internal(file_path, connection_limit, SqliteParams::default(), test_mode)
+    }
+
+    /// Opens `file_path` like [`Sqlite::new`], then brings the `db`-attached schema up to
+    /// `target_version` by running whichever of `migrations` haven't been applied yet, tracked
+    /// via SQLite's `PRAGMA user_version`. `migrations[i]` takes the schema from version `i` to
+    /// `i + 1`, and runs exactly once per physical database file: each migration commits in its
+    /// own transaction that also bumps `user_version`, so calling this again against the same
+    /// file is a no-op once it has caught up. `pre_migration`, when given, runs once with the
+    /// database's current version before any migration does, e.g. to run an integrity check.
+    /// `db` should be the same name passed to [`Database::with_connection`] and friends
+    /// afterwards, since that's what the migrated schema ends up attached as.
+    pub fn open_database(
+        file_path: String,
+        db: &str,
+        connection_limit: u32,
+        migrations: Vec<Migration>,
+        target_version: i64,
+        pre_migration: Option<Box<dyn Fn(i64) -> crate::Result<()> + Send + Sync>>,
+        test_mode: bool,
+    ) -> crate::Result<Sqlite> {
+        let sqlite = Sqlite::new_internal(file_path, connection_limit, SqliteParams::default(), test_mode)?;
+
+        let mut conn = sqlite.pool.get()?;
+        sqlite.attach_database(&mut conn, db)?;
+        migrations::run_pending(&mut conn, db, &migrations, target_version, pre_migration.as_deref())?;
+
+        Ok(sqlite)
+    }
+
+    // `main` is always an in-memory, scratch schema -- `file_path` itself is only ever opened
+    // through `attach_database`'s `ATTACH DATABASE`, under `db_name`. Opening `file_path` a
+    // second time here, as `main`, would give the process two OS file descriptors onto the same
+    // physical file; POSIX advisory locks are scoped to (process, inode), so a lock acquired or
+    // released through one fd can silently cancel one held through the other, which is exactly
+    // the corruption hazard SQLite's own docs warn about for pooled/concurrent writers. Routing
+    // everything through a single attach keeps exactly one fd per physical file per connection.
+    fn new_internal(
+        file_path: String,
+        connection_limit: u32,
+        params: SqliteParams,
+        test_mode: bool,
+    ) -> crate::Result<Sqlite> {
+        // `rusqlite`'s default `OpenFlags` already include `SQLITE_OPEN_URI`, so the `file:`
+        // URIs `SqliteParams::attach_uri` builds are recognized by the `ATTACH DATABASE` below
+        // without needing to request that flag again here.
+        let manager = SqliteConnectionManager::memory().with_init(move |conn| {
+            // Bootstrap PRAGMAs applied identically to every connection the pool hands out, so
+            // this is the single place that configures how a fresh connection behaves instead of
+            // leaving it to whoever happens to touch the connection first. These are
+            // connection-wide, unlike `journal_mode`/`synchronous`, which are per-schema and so
+            // are applied to the attached schema instead, in `attach_database`.
+            conn.pragma_update(None, "foreign_keys", true)?;
+            conn.busy_timeout(DEFAULT_BUSY_TIMEOUT)?;
+
+            Ok(())
+        });
+
+        let pool = r2d2::Pool::builder().max_size(connection_limit).build(manager)?;
 
         Ok(Sqlite {
             file_path,
             pool,
             test_mode,
+            scalar_functions: Default::default(),
+            params,
+            max_insert_rows: None,
+            max_bind_values: Some(DEFAULT_MAX_BIND_VALUES),
         })
     }
 
@@ -112,6 +347,208 @@ impl Sqlite {
         path.exists()
     }
 
+    /// Whether `insert`'s row count would exceed `max_insert_rows` or push the number of bound
+    /// parameters past `max_bind_values` if sent as a single statement.
+    fn insert_needs_chunking(&self, insert: &Insert<'_>) -> bool {
+        if insert.values.is_empty() {
+            return false;
+        }
+
+        let columns = insert.columns.len().max(1);
+        let row_count = insert.values.len();
+
+        self.max_insert_rows.map_or(false, |max| row_count > max)
+            || self.max_bind_values.map_or(false, |max| row_count * columns > max)
+    }
+
+    /// Splits `insert` into the largest chunks that satisfy both `max_insert_rows` and the
+    /// bind-value ceiling implied by `max_bind_values / columns_per_row`, then executes each
+    /// chunk in turn on `conn`, returning the last chunk's insert id. The caller is expected to
+    /// run this inside a transaction so the whole operation stays atomic even though it's split
+    /// into several statements under the hood.
+    fn execute_chunked_insert<'a>(&self, conn: &mut Queryable, insert: Insert<'a>) -> crate::Result<Option<Id>> {
+        let columns = insert.columns.len().max(1);
+        let chunk_size = Self::each_chunk_size(insert.values.len(), columns, self.max_insert_rows, self.max_bind_values);
+
+        let mut last_id = None;
+
+        for chunk in insert.values.chunks(chunk_size) {
+            let chunked = Insert {
+                values: chunk.to_vec(),
+                ..insert.clone()
+            };
+
+            if let Some(id) = conn.execute(Query::Insert(Box::new(chunked)))? {
+                last_id = Some(id);
+            }
+        }
+
+        Ok(last_id)
+    }
+
+    /// Largest number of rows an `INSERT` with `columns_per_row` columns can carry in a single
+    /// statement without exceeding either `max_insert_rows` or the bind-parameter ceiling
+    /// implied by `max_bind_values` (SQLite's default is 999 bound parameters per statement).
+    fn each_chunk_size(
+        row_count: usize,
+        columns_per_row: usize,
+        max_insert_rows: Option<usize>,
+        max_bind_values: Option<usize>,
+    ) -> usize {
+        let by_bind_values = max_bind_values
+            .map(|max| (max / columns_per_row.max(1)).max(1))
+            .unwrap_or(usize::MAX);
+
+        max_insert_rows
+            .unwrap_or(usize::MAX)
+            .min(by_bind_values)
+            .min(row_count.max(1))
+    }
+
+    /// Opens a streaming handle onto a single BLOB value at `(table, column, row_id)`, so large
+    /// binary payloads can be read or written incrementally instead of being materialized as a
+    /// single `ParameterizedValue`. The returned [`BlobHandle`] implements `Read`, `Write` and
+    /// `Seek`, and keeps a pooled connection checked out for as long as it's alive. `table` is
+    /// looked up in the `db`-attached schema (see the note on [`Sqlite`]), so it must already
+    /// exist there -- a `table` created through unqualified SQL, which lands in `main`, won't be
+    /// found.
+    pub fn open_blob(&self, db: &str, table: &str, column: &str, row_id: i64, read_only: bool) -> crate::Result<BlobHandle> {
+        let mut conn = self.pool.get()?;
+        self.attach_database(&mut conn, db)?;
+
+        BlobHandle::new(conn, db, table, column, row_id, read_only)
+    }
+
+    /// Takes a hot backup of the `db`-attached schema into `dest_path` using SQLite's online
+    /// backup API. The source connection stays usable for reads and writes while pages are
+    /// copied over in small steps, so this is safe to run against a database that is serving
+    /// traffic.
+    pub fn backup(&self, db: &str, dest_path: &str) -> crate::Result<()> {
+        self.backup_with_progress(db, dest_path, DEFAULT_BACKUP_PAGES_PER_STEP, |_, _| {})
+    }
+
+    /// Same as [`Sqlite::backup`], but calls `progress(remaining, total)` after every step so
+    /// callers can report copy progress, and lets the caller tune how many pages are copied per
+    /// step.
+    pub fn backup_with_progress<F>(&self, db: &str, dest_path: &str, pages_per_step: i32, progress: F) -> crate::Result<()>
+    where
+        F: FnMut(i32, i32),
+    {
+        let mut src = self.pool.get()?;
+        self.attach_database(&mut src, db)?;
+        let mut dest = rusqlite::Connection::open(dest_path)?;
+
+        Self::run_backup(
+            &src,
+            rusqlite::DatabaseName::Attached(db),
+            &mut dest,
+            rusqlite::DatabaseName::Main,
+            pages_per_step,
+            progress,
+        )
+    }
+
+    /// Restores the `db`-attached schema from the backup file at `src_path`, overwriting its
+    /// current contents page by page via SQLite's online backup API.
+    pub fn restore(&self, db: &str, src_path: &str) -> crate::Result<()> {
+        self.restore_with_progress(db, src_path, DEFAULT_BACKUP_PAGES_PER_STEP, |_, _| {})
+    }
+
+    /// Same as [`Sqlite::restore`], but calls `progress(remaining, total)` after every step.
+    pub fn restore_with_progress<F>(&self, db: &str, src_path: &str, pages_per_step: i32, progress: F) -> crate::Result<()>
+    where
+        F: FnMut(i32, i32),
+    {
+        let src = rusqlite::Connection::open(src_path)?;
+        let mut dest = self.pool.get()?;
+        self.attach_database(&mut dest, db)?;
+
+        Self::run_backup(
+            &src,
+            rusqlite::DatabaseName::Main,
+            &mut dest,
+            rusqlite::DatabaseName::Attached(db),
+            pages_per_step,
+            progress,
+        )
+    }
+
+    fn run_backup<F>(
+        src: &rusqlite::Connection,
+        src_name: rusqlite::DatabaseName,
+        dest: &mut rusqlite::Connection,
+        dest_name: rusqlite::DatabaseName,
+        pages_per_step: i32,
+        mut progress: F,
+    ) -> crate::Result<()>
+    where
+        F: FnMut(i32, i32),
+    {
+        let backup = Backup::new_with_names(src, src_name, dest, dest_name)?;
+
+        loop {
+            use rusqlite::backup::StepResult;
+
+            let step = backup.step(pages_per_step)?;
+            let p = backup.progress();
+            progress(p.remaining, p.pagecount);
+
+            match step {
+                StepResult::Done => return Ok(()),
+                StepResult::More => (),
+                StepResult::Busy | StepResult::Locked => thread::sleep(BACKUP_RETRY_DELAY),
+            }
+        }
+    }
+
+    /// Registers a scalar function under `name` so it becomes callable from SQL run through
+    /// `query`, `execute` and `query_raw` on any connection handed out by the pool, including
+    /// ones checked out after this call. Useful for things like a custom `REGEXP`, hashing, or
+    /// other domain-specific predicates generated SQL can call into.
+    pub fn create_scalar_function<F>(&self, name: &str, n_args: i32, deterministic: bool, func: F) -> crate::Result<()>
+    where
+        F: Fn(&[ParameterizedValue<'_>]) -> crate::Result<ParameterizedValue<'static>> + Send + Sync + 'static,
+    {
+        let scalar_fn = ScalarFunction {
+            name: name.to_string(),
+            n_args,
+            deterministic,
+            func: Arc::new(func),
+        };
+
+        // Make it callable on a fresh connection right away, then remember it so it gets
+        // re-applied to every connection the pool hands out from here on.
+        Self::register_scalar_function(&self.pool.get()?, &scalar_fn)?;
+        self.scalar_functions.lock().unwrap().push(scalar_fn);
+
+        Ok(())
+    }
+
+    fn register_scalar_function(conn: &rusqlite::Connection, scalar_fn: &ScalarFunction) -> crate::Result<()> {
+        let mut flags = FunctionFlags::SQLITE_UTF8;
+
+        if scalar_fn.deterministic {
+            flags |= FunctionFlags::SQLITE_DETERMINISTIC;
+        }
+
+        let func = scalar_fn.func.clone();
+
+        conn.create_scalar_function(&scalar_fn.name, scalar_fn.n_args, flags, move |ctx| {
+            let args: Vec<ParameterizedValue> = (0..ctx.len())
+                .map(|i| conversion::value_from_function_arg(ctx, i))
+                .collect::<rusqlite::Result<_>>()?;
+
+            let result = func(&args).map_err(|e| rusqlite::Error::UserFunctionError(Box::new(e)))?;
+
+            Ok(result)
+        })?;
+
+        Ok(())
+    }
+
+    /// Attaches `file_path` under `db_name` if it isn't already, so that `db_name`-qualified SQL
+    /// reaches it. See the note on [`Sqlite`] -- this never makes `file_path`'s data reachable
+    /// through unqualified (`main`) table names.
     fn attach_database(&self, conn: &mut rusqlite::Connection, db_name: &str) -> crate::Result<()> {
         let mut stmt = conn.prepare("PRAGMA database_list")?;
 
@@ -125,34 +562,125 @@ impl Sqlite {
             .collect();
 
         if !databases.contains(db_name) {
-            rusqlite::Connection::execute(
-                conn,
-                "ATTACH DATABASE ? AS ?",
-                &[self.file_path.as_ref(), db_name],
-            )?;
+            let attach_uri = self.params.attach_uri(&self.file_path);
+
+            rusqlite::Connection::execute(conn, "ATTACH DATABASE ? AS ?", &[attach_uri.as_str(), db_name])?;
+
+            // `journal_mode` and `synchronous` are per-schema, so they only take effect once
+            // there's a real schema attached to apply them to -- unlike `foreign_keys` and
+            // `busy_timeout`, which are connection-wide and so are set up front in `with_init`.
+            let schema = rusqlite::DatabaseName::Attached(db_name);
+            conn.pragma_update(Some(schema), "journal_mode", "WAL")?;
+            conn.pragma_update(Some(schema), "synchronous", "NORMAL")?;
+
+            if self.params.immutable {
+                conn.pragma_update(Some(schema), "query_only", true)?;
+            }
         }
 
-        rusqlite::Connection::execute(conn, "PRAGMA foreign_keys = ON", NO_PARAMS)?;
         Ok(())
     }
 
-    fn with_connection_internal<F, T>(&self, db: &str, f: F) -> crate::Result<T>
+    /// Runs `f` against `db` inside its own transaction, attributing every statement to
+    /// `traceparent` if given. See [`Sqlite::with_connection_traced`] for why `traceparent` is
+    /// threaded through as an argument here rather than read off shared state.
+    fn with_transaction_internal<F, T>(&self, db: &str, traceparent: Option<TraceParent>, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Queryable) -> crate::Result<T>,
+    {
+        self.with_connection_internal(db, traceparent, |conn| {
+            let tx = conn.transaction()?;
+            tx.set_prepared_statement_cache_capacity(65536);
+
+            let mut connection_like = ConnectionLike::from(tx);
+            let result = f(&mut connection_like);
+
+            if result.is_ok() {
+                let tx = rusqlite::Transaction::try_from(connection_like).unwrap();
+                tx.commit()?;
+            }
+
+            result
+        })
+    }
+
+    /// Like [`Database::with_connection`], but attributes every statement run during `f` to a
+    /// child span of `traceparent`. `traceparent` is an argument scoped to this single call rather
+    /// than state stored on the connector, so two callers using the same
+    /// pooled [`Sqlite`] concurrently can never have their queries attributed to each other's
+    /// trace.
+    pub fn with_connection_traced<F, T>(&self, db: &str, traceparent: TraceParent, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Queryable) -> crate::Result<T>,
+    {
+        self.with_connection_internal(db, Some(traceparent), |c| f(c))
+    }
+
+    /// Like [`Transactional::with_transaction`], but attributes every statement run during `f` to
+    /// a child span of `traceparent`. See [`Sqlite::with_connection_traced`].
+    pub fn with_transaction_traced<F, T>(&self, db: &str, traceparent: TraceParent, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&mut Queryable) -> crate::Result<T>,
+    {
+        self.with_transaction_internal(db, Some(traceparent), f)
+    }
+
+    fn with_connection_internal<F, T>(&self, db: &str, traceparent: Option<TraceParent>, f: F) -> crate::Result<T>
     where
         F: FnOnce(&mut ConnectionLike) -> crate::Result<T>,
     {
         let mut conn = self.pool.get()?;
         self.attach_database(&mut conn, db)?;
 
+        for scalar_fn in self.scalar_functions.lock().unwrap().iter() {
+            Self::register_scalar_function(&conn, scalar_fn)?;
+        }
+
+        if let Some(traceparent) = traceparent {
+            Self::install_telemetry_hooks(&mut conn, traceparent);
+        }
+
         let mut connection_like = ConnectionLike::from(conn);
         let result = f(&mut connection_like);
 
-        if self.test_mode {
-            let conn = PooledConnection::try_from(connection_like).unwrap();
-            conn.execute("DETACH DATABASE ?", &[db])?;
+        // The hooks installed above are only valid for this borrow of the connection, and the
+        // connection itself goes back to the pool and may be handed to an unrelated caller next
+        // -- so they must come off before that happens regardless of test_mode, or a later
+        // caller with no traceparent of its own would silently keep emitting spans parented to
+        // this one.
+        if self.test_mode || traceparent.is_some() {
+            let mut conn = PooledConnection::try_from(connection_like).unwrap();
+
+            if traceparent.is_some() {
+                Self::remove_telemetry_hooks(&mut conn);
+            }
+
+            if self.test_mode {
+                conn.execute("DETACH DATABASE ?", &[db])?;
+            }
         }
 
         result
     }
+
+    /// Installs a `Connection::trace` and `Connection::profile` hook pair that turn every
+    /// statement run on `conn` into a child span of `traceparent`, via the `telemetry` crate.
+    /// Scoped to a single borrowed connection so it only covers the closure it's installed
+    /// around; paired with [`Sqlite::remove_telemetry_hooks`].
+    fn install_telemetry_hooks(conn: &mut rusqlite::Connection, traceparent: TraceParent) {
+        conn.trace(Some(Box::new(move |sql| {
+            telemetry::event!(parent: traceparent, "sqlite.trace", sql = sql);
+        })));
+
+        conn.profile(Some(Box::new(move |sql, duration| {
+            telemetry::event!(parent: traceparent, "sqlite.profile", sql = sql, duration_ms = duration.as_secs_f64() * 1000.0);
+        })));
+    }
+
+    fn remove_telemetry_hooks(conn: &mut rusqlite::Connection) {
+        conn.trace(None);
+        conn.profile(None);
+    }
 }
 
 #[cfg(test)]
@@ -229,4 +757,197 @@ mod tests {
             })
             .unwrap()
     }
+
+    #[test]
+    fn should_run_pending_migrations_against_the_attached_schema() {
+        // Each migration is handed the db name it must qualify its DDL with -- unqualified SQL
+        // would resolve to main, which is always in-memory scratch and discarded on close.
+        let migrations: Vec<Migration> = vec![
+            Box::new(|tx, db| {
+                tx.execute(
+                    &format!("CREATE TABLE {}.USER (ID INTEGER PRIMARY KEY, NAME TEXT NOT NULL)", db),
+                    NO_PARAMS,
+                )?;
+                Ok(())
+            }),
+            Box::new(|tx, db| {
+                tx.execute(&format!("ALTER TABLE {}.USER ADD COLUMN AGE INTEGER", db), NO_PARAMS)?;
+                Ok(())
+            }),
+        ];
+
+        let connector = Sqlite::open_database(String::from("db/migration_test.db"), "test", 1, migrations, 2, None, true).unwrap();
+
+        connector
+            .with_connection("test", |connection| {
+                connection.query_raw("INSERT INTO test.USER (ID, NAME, AGE) VALUES (1, 'Joe', 27)", &[])?;
+
+                let rows = connection.query_raw("SELECT * FROM test.USER", &[])?;
+                assert_eq!(rows.len(), 1);
+                assert_eq!(rows.get(0).unwrap()["AGE"].as_i64(), Some(27));
+
+                Ok(())
+            })
+            .unwrap();
+
+        // Re-opening against the same file with the same target version should be a no-op: no
+        // migrations are passed this time, so run_pending would fail outright (no migration
+        // registered for the step) if it read a version below 2, so getting here at all proves
+        // user_version was picked up off the attached
+        // schema rather than the always-empty main -- and that the table the migrations created
+        // is actually there to find.
+        let reopened = Sqlite::open_database(String::from("db/migration_test.db"), "test", 1, Vec::new(), 2, None, true).unwrap();
+
+        reopened
+            .with_connection("test", |connection| {
+                let rows = connection.query_raw("SELECT * FROM test.USER", &[])?;
+                assert_eq!(rows.len(), 1);
+
+                Ok(())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn should_read_and_write_through_a_blob_handle() {
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let connector = Sqlite::new(String::from("db/blob_test.db"), 1, true).unwrap();
+
+        // Qualified with the attached schema name: unqualified DDL/DML lands in `main`, which
+        // `open_blob` below never looks at -- see the note on `Sqlite`.
+        connector
+            .with_connection("test", |connection| {
+                connection.query_raw(
+                    "CREATE TABLE test.FILES (ID INTEGER PRIMARY KEY, CONTENT BLOB NOT NULL)",
+                    &[],
+                )?;
+                connection.query_raw("INSERT INTO test.FILES (ID, CONTENT) VALUES (1, zeroblob(5))", &[])?;
+
+                Ok(())
+            })
+            .unwrap();
+
+        let mut blob = connector.open_blob("test", "FILES", "CONTENT", 1, false).unwrap();
+        assert_eq!(blob.len(), 5);
+
+        blob.write_all(b"hello").unwrap();
+        blob.seek(SeekFrom::Start(0)).unwrap();
+
+        let mut buf = [0u8; 5];
+        blob.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[test]
+    fn sqlite_params_parses_uri_query_and_builds_the_attach_uri() {
+        let params = SqliteParams::parse(Some("mode=rwc&cache=shared&immutable=1&connection_limit=5"));
+
+        assert!(matches!(params.mode, Some(OpenMode::ReadWriteCreate)));
+        assert!(matches!(params.cache, Some(CacheMode::Shared)));
+        assert!(params.immutable);
+        assert_eq!(params.connection_limit, Some(5));
+
+        let uri = params.attach_uri("db/test.db");
+        assert!(uri.starts_with("file:db/test.db?"));
+        assert!(uri.contains("mode=rwc"));
+        assert!(uri.contains("cache=shared"));
+        assert!(uri.contains("immutable=1"));
+    }
+
+    #[test]
+    fn sqlite_params_leaves_memory_databases_unwrapped() {
+        let params = SqliteParams::parse(Some("mode=memory"));
+        assert_eq!(params.attach_uri(":memory:"), ":memory:");
+    }
+
+    #[test]
+    fn should_open_a_file_backed_database_via_uri_params() {
+        let connector = Sqlite::try_from("file:db/uri_test.db?mode=rwc&cache=private").unwrap();
+
+        connector
+            .with_connection("test", |connection| {
+                connection.query_raw(TABLE_DEF, &[])?;
+                connection.query_raw(CREATE_USER, &[])?;
+
+                let rows = connection.query_raw("SELECT * FROM USER", &[])?;
+                assert_eq!(rows.len(), 1);
+
+                Ok(())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn should_register_and_call_a_scalar_function() {
+        let connector = Sqlite::new(String::from("db/test.db"), 1, true).unwrap();
+
+        connector
+            .create_scalar_function("DOUBLE_IT", 1, true, |args| match &args[0] {
+                ParameterizedValue::Integer(i) => Ok(ParameterizedValue::Integer(i * 2)),
+                other => Ok(other.clone()),
+            })
+            .unwrap();
+
+        connector
+            .with_connection("test", |connection| {
+                let rows = connection.query_raw("SELECT DOUBLE_IT(21) AS doubled", &[])?;
+                assert_eq!(rows.get(0).unwrap()["doubled"].as_i64(), Some(42));
+
+                Ok(())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn should_backup_and_restore_through_the_attached_schema() {
+        let connector = Sqlite::new(String::from("db/backup_test.db"), 1, true).unwrap();
+
+        // Qualified with the attached schema name: TABLE_DEF/CREATE_USER are unqualified and would
+        // land in main, which backup() never copies from -- see the note on `Sqlite`.
+        connector
+            .with_connection("test", |connection| {
+                connection.query_raw(
+                    "CREATE TABLE test.USER (ID INT PRIMARY KEY NOT NULL, NAME TEXT NOT NULL, AGE INT NOT NULL, SALARY REAL)",
+                    &[],
+                )?;
+                connection.query_raw(
+                    "INSERT INTO test.USER (ID,NAME,AGE,SALARY) VALUES (1, 'Joe', 27, 20000.00)",
+                    &[],
+                )?;
+
+                Ok(())
+            })
+            .unwrap();
+
+        connector.backup("test", "db/backup_test_copy.db").unwrap();
+
+        let restored = Sqlite::new(String::from("db/backup_test_restored.db"), 1, true).unwrap();
+        restored.restore("test", "db/backup_test_copy.db").unwrap();
+
+        restored
+            .with_connection("test", |connection| {
+                let rows = connection.query_raw("SELECT * FROM test.USER", &[])?;
+                assert_eq!(rows.len(), 1);
+                assert_eq!(rows.get(0).unwrap()["NAME"].as_str(), Some("Joe"));
+
+                Ok(())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn each_chunk_size_respects_the_tighter_of_both_limits() {
+        // Bind-value ceiling is the binding constraint: 999 / 3 columns = 333 rows per chunk.
+        assert_eq!(Sqlite::each_chunk_size(1000, 3, None, Some(999)), 333);
+
+        // max_insert_rows is the binding constraint.
+        assert_eq!(Sqlite::each_chunk_size(1000, 3, Some(100), Some(999)), 100);
+
+        // Neither limit set: the whole batch fits in one chunk.
+        assert_eq!(Sqlite::each_chunk_size(10, 3, None, None), 10);
+
+        // Fewer rows than either limit allows: still capped at the actual row count.
+        assert_eq!(Sqlite::each_chunk_size(2, 3, Some(100), Some(999)), 2);
+    }
 }